@@ -35,33 +35,101 @@ use embedded_hal::i2c::I2c;
 
 #[cfg(feature = "graphics")]
 use embedded_graphics_core::{
-    draw_target::DrawTarget, geometry::OriginDimensions, geometry::Size, pixelcolor::Gray4,
-    pixelcolor::GrayColor, Pixel,
+    draw_target::DrawTarget, geometry::OriginDimensions, geometry::Point, geometry::Size,
+    pixelcolor::Gray4, pixelcolor::GrayColor, Pixel,
 };
 
-/// SSD1327 I2C driver container
-pub struct SSD1327I2C<I2C> {
+/// SSD1327 I2C driver container.
+///
+/// `N` is the framebuffer's byte capacity (two 4-bit pixels per byte) and
+/// defaults to `8192` (128x128). The panel's actual pixel width/height are
+/// runtime values passed to `with_addr_wh`/`with_wh`, checked against `N` at
+/// construction time; for a smaller module, pick a smaller `N` via
+/// turbofish, e.g. `SSD1327I2C::<I2C, { 96 / 2 * 96 }>::with_wh(i2c, 96, 96)`.
+///
+/// Note this is a single capacity const generic, not `SSD1327I2C<I2C, W,
+/// H>` with `framebuffer: [u8; (W/2)*H]` as an array length computed from
+/// two independent const generics: that shape requires the unstable
+/// `generic_const_exprs` feature and still fails to compile (the compiler
+/// reports the array length as an unconstrained generic constant even with
+/// the feature enabled). Turbofish call sites written against a `W, H` API
+/// need to switch to passing a single precomputed capacity as shown above.
+/// The capacity is checked against `width`/`height` at construction time via
+/// a real `assert!` (not `debug_assert!`), so an undersized `N` fails
+/// immediately there instead of surfacing later as an out-of-bounds panic
+/// inside `draw_iter`/`flush`.
+pub struct SSD1327I2C<I2C, const N: usize = 8192> {
     i2c: I2C,
     slave_address: u8,
+    /// Column byte stride: ceil(pixel_width / 2), two pixels per byte
     width: u8,
+    /// The actual panel pixel width passed to `with_addr_wh`/`with_wh`,
+    /// independent of `width`'s rounding to a whole byte stride. Only
+    /// read back by `px_width()`, which is graphics-only.
+    #[cfg(feature = "graphics")]
+    pixel_width: u8,
     height: u8,
     #[cfg(feature = "graphics")]
-    framebuffer: [u8; 128 * 64],
+    framebuffer: [u8; N],
+    #[cfg(not(feature = "graphics"))]
+    _capacity: core::marker::PhantomData<[(); N]>,
+    /// Bounding box (in pixel coordinates) of the framebuffer area written
+    /// since the last `flush()`. An empty box is represented by
+    /// `dirty_min_x > dirty_max_x` (or the equivalent for y).
+    #[cfg(feature = "graphics")]
+    dirty_min_x: u8,
+    #[cfg(feature = "graphics")]
+    dirty_min_y: u8,
+    #[cfg(feature = "graphics")]
+    dirty_max_x: u8,
+    #[cfg(feature = "graphics")]
+    dirty_max_y: u8,
+    #[cfg(feature = "graphics")]
+    rotation: Rotation,
+    /// Logical RAM row currently mapped to the first visible COM line, set
+    /// via `scroll_vertical`/`reset_scroll`.
+    #[cfg(feature = "graphics")]
+    scroll_offset: u8,
 }
 
-impl<I2C: I2c> SSD1327I2C<I2C> {
+impl<I2C: I2c, const N: usize> SSD1327I2C<I2C, N> {
     /// Create a new SSD1327I2C object with custom slave adress, width and height
     pub fn with_addr_wh(i2c: I2C, slave_address: u8, width: u8, height: u8) -> Self {
+        let halfwidth = width.div_ceil(2); // Two pixels per byte, rounded up
+        // A real (not debug_assert!) panic: this runs once at construction,
+        // not on a hot path, and a too-small N must fail clearly here rather
+        // than as a confusing out-of-bounds index later inside draw_iter/flush.
         #[cfg(feature = "graphics")]
-        let framebuffer = [0u8; 128 * 64];
-        let halfwidth = width / 2; // Two pixels per byte
+        assert!(
+            (halfwidth as usize) * (height as usize) <= N,
+            "framebuffer capacity N is too small for the requested width/height"
+        );
+        #[cfg(feature = "graphics")]
+        let framebuffer = [0u8; N];
         SSD1327I2C {
             i2c,
             slave_address,
             width: halfwidth,
+            #[cfg(feature = "graphics")]
+            pixel_width: width,
             height,
             #[cfg(feature = "graphics")]
             framebuffer,
+            #[cfg(not(feature = "graphics"))]
+            _capacity: core::marker::PhantomData,
+            // Start out fully dirty so the first `flush()` paints the whole panel.
+            #[cfg(feature = "graphics")]
+            dirty_min_x: 0,
+            #[cfg(feature = "graphics")]
+            dirty_min_y: 0,
+            #[cfg(feature = "graphics")]
+            dirty_max_x: width.saturating_sub(1),
+            #[cfg(feature = "graphics")]
+            dirty_max_y: height.saturating_sub(1),
+            #[cfg(feature = "graphics")]
+            rotation: Rotation::Rotate0,
+            #[cfg(feature = "graphics")]
+            scroll_offset: 0,
         }
     }
 
@@ -114,6 +182,15 @@ impl<I2C: I2c> SSD1327I2C<I2C> {
 
     /// Write command to the SSD1327
     pub fn send_cmd(&mut self, cmd: Commands) -> Result<(), I2C::Error> {
+        // The grayscale table carries 15 payload bytes, more than the 4-byte
+        // buffer below can hold, so it gets its own encoding path.
+        if let Commands::GrayScaleTable(levels) = cmd {
+            let mut data = [0u8; 17];
+            data[0] = 0x00;
+            data[1] = 0xB8;
+            data[2..17].copy_from_slice(&levels);
+            return self.send_bytes(&data);
+        }
         // 0x00 = Command (Don't know why it's not 0x80)
         let (data, len) = match cmd {
             Commands::ColumnAddress { start, end } => ([0x00, 0x15, start, end], 4),
@@ -143,6 +220,9 @@ impl<I2C: I2c> SSD1327I2C<I2C> {
             Commands::SetCommandLock(value) => ([0x00, 0xFD, value, 0], 3),
             Commands::CommandUnlock => ([0x00, 0xFD, 0x00, 0x12], 4),
             Commands::CommandLock => ([0x00, 0xFD, 0x00, 0x16], 4),
+            // Already handled and returned above; the 4-byte buffer here
+            // can't hold its 15-byte payload.
+            Commands::GrayScaleTable(_) => unreachable!(),
         };
         self.send_bytes(&data[0..len])
     }
@@ -165,47 +245,257 @@ impl<I2C: I2c> SSD1327I2C<I2C> {
     }
 
     #[cfg(feature = "graphics")]
-    /// Write 8 bytes of data to the SSD1327
-    fn send_buffer_data(&mut self, index: usize) -> Result<(), I2C::Error> {
+    /// Write up to 8 bytes of data starting at the given framebuffer index
+    fn send_buffer_data(&mut self, index: usize, len: usize) -> Result<(), I2C::Error> {
         // 0x40 = Data
-        let bytes = [
-            0x40,
-            self.framebuffer[index],
-            self.framebuffer[index + 1],
-            self.framebuffer[index + 2],
-            self.framebuffer[index + 3],
-            self.framebuffer[index + 4],
-            self.framebuffer[index + 5],
-            self.framebuffer[index + 6],
-            self.framebuffer[index + 7],
-        ];
-        self.send_bytes(&bytes)
+        let mut bytes = [0u8; 9];
+        bytes[0] = 0x40;
+        bytes[1..=len].copy_from_slice(&self.framebuffer[index..index + len]);
+        self.send_bytes(&bytes[0..=len])
+    }
+
+    #[cfg(feature = "graphics")]
+    /// The panel's pixel width, as passed to `with_addr_wh`/`with_wh`
+    fn px_width(&self) -> u32 {
+        self.pixel_width as u32
+    }
+
+    #[cfg(feature = "graphics")]
+    /// The panel's pixel height
+    fn px_height(&self) -> u32 {
+        self.height as u32
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Grow the dirty rectangle to include the given pixel coordinate
+    fn mark_dirty(&mut self, x: u8, y: u8) {
+        if x < self.dirty_min_x {
+            self.dirty_min_x = x;
+        }
+        if x > self.dirty_max_x {
+            self.dirty_max_x = x;
+        }
+        if y < self.dirty_min_y {
+            self.dirty_min_y = y;
+        }
+        if y > self.dirty_max_y {
+            self.dirty_max_y = y;
+        }
     }
 
     #[cfg(feature = "graphics")]
-    /// Update the display with the current framebuffer
+    /// Mark the dirty rectangle as empty, i.e. nothing to flush
+    fn clear_dirty_rect(&mut self) {
+        self.dirty_min_x = self.px_width().saturating_sub(1) as u8;
+        self.dirty_min_y = self.px_height().saturating_sub(1) as u8;
+        self.dirty_max_x = 0;
+        self.dirty_max_y = 0;
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Update the display with the framebuffer bytes inside the dirty rectangle
+    /// accumulated since the last `flush()`/`flush_all()`. Does nothing if no
+    /// pixel has been written.
     pub fn flush(&mut self) -> Result<(), I2C::Error> {
+        if self.dirty_min_x > self.dirty_max_x || self.dirty_min_y > self.dirty_max_y {
+            return Ok(());
+        }
+        let col_start = self.dirty_min_x / 2;
+        let col_end = self.dirty_max_x / 2;
         self.send_cmd(Commands::ColumnAddress {
-            start: 0x00,
-            end: self.width,
+            start: col_start,
+            end: col_end,
         })
-        .ok(); //0-63
+        .ok();
         self.send_cmd(Commands::RowAddress {
-            start: 0x00,
-            end: self.height,
+            start: self.dirty_min_y,
+            end: self.dirty_max_y,
         })
-        .ok(); //0-127
+        .ok();
+        let stride = self.width as usize;
         let mut res: Result<(), I2C::Error> = Ok(());
-        for y in 0..=127 {
-            for x in (0..=63).step_by(8) {
-                match self.send_buffer_data(x + y * 64) {
+        for y in self.dirty_min_y..=self.dirty_max_y {
+            let mut x = col_start;
+            while x <= col_end {
+                let chunk = (col_end - x + 1).min(8);
+                match self.send_buffer_data(x as usize + y as usize * stride, chunk as usize) {
                     Ok(_) => (),
                     Err(e) => res = Err(e),
                 }
+                x += chunk;
             }
         }
+        self.clear_dirty_rect();
         res
     }
+
+    #[cfg(feature = "graphics")]
+    /// Force a full redraw of the panel, ignoring the dirty rectangle. Useful
+    /// after re-init or whenever the tracked dirty region can't be trusted.
+    pub fn flush_all(&mut self) -> Result<(), I2C::Error> {
+        self.dirty_min_x = 0;
+        self.dirty_min_y = 0;
+        self.dirty_max_x = self.px_width().saturating_sub(1) as u8;
+        self.dirty_max_y = self.px_height().saturating_sub(1) as u8;
+        self.flush()
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Rotate subsequent `embedded-graphics` content by `rotation`. This
+    /// reprograms `Commands::Remap` and changes how `draw_iter` maps
+    /// coordinates into the framebuffer, so content comes out upright no
+    /// matter how the panel is mounted. Call `init()` first, then this.
+    pub fn set_rotation(&mut self, rotation: Rotation) -> Result<(), I2C::Error> {
+        self.rotation = rotation;
+        // Base bits common to every orientation: nibble re-map off, COM
+        // split odd/even on (A[6]), matching the driver's `init()` default.
+        const BASE_REMAP: u8 = 0b0100_0000;
+        let remap = BASE_REMAP
+            | match rotation {
+                // A[0] column re-map, A[4] COM re-map. Every rotation is
+                // applied entirely by draw_iter's coordinate transform below,
+                // not by reprogramming GDDRAM addressing: `flush` always
+                // streams bytes assuming horizontal increment (A[2]=0), so
+                // toggling A[2] here would desync the burst writes from the
+                // addressing mode and corrupt the display, and clearing
+                // A[0]/A[4] for 180° would mirror both axes a second time on
+                // top of draw_iter's own 180° transform. Keep the 0° bits for
+                // every rotation.
+                Rotation::Rotate0
+                | Rotation::Rotate90
+                | Rotation::Rotate180
+                | Rotation::Rotate270 => 0b0001_0001,
+            };
+        self.send_cmd(Commands::Remap(remap))?;
+        self.flush_all()
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Blit a packed 4bpp grayscale image (two pixels per byte, row-major, as
+    /// produced by e.g. `convert img.png -depth 4 gray:img.raw`) directly
+    /// into the framebuffer, bypassing `draw_iter`/`embedded-graphics`. The
+    /// number of rows is inferred from `data.len()`. Anything past the
+    /// panel's pixel bounds is clipped; a negative `top_left` is ignored.
+    ///
+    /// `top_left` and `width` are in raw physical framebuffer coordinates:
+    /// unlike `draw_iter`, this does not consult `rotation`. The nibble
+    /// packing this relies on to copy whole bytes at a time only lines up in
+    /// the panel's native (unrotated) orientation, so after `set_rotation`
+    /// with anything other than `Rotate0`, callers must rotate `data` and
+    /// `top_left` themselves before calling this.
+    pub fn draw_raw_gray4(&mut self, top_left: Point, width: u32, data: &[u8]) {
+        if top_left.x < 0 || top_left.y < 0 {
+            return;
+        }
+        let tx = top_left.x as u32;
+        let ty = top_left.y as u32;
+        if tx >= self.px_width() || ty >= self.px_height() || width == 0 {
+            return;
+        }
+        let src_row_bytes = (width as usize).div_ceil(2);
+        if data.len() < src_row_bytes {
+            return;
+        }
+        let rows = (data.len() / src_row_bytes) as u32;
+        let copy_width = width.min(self.px_width() - tx);
+        let copy_rows = rows.min(self.px_height() - ty);
+        if copy_width == 0 || copy_rows == 0 {
+            return;
+        }
+        let stride = self.width as usize;
+
+        for row in 0..copy_rows {
+            let dest_row_start = (ty + row) as usize * stride;
+            let src_row_start = row as usize * src_row_bytes;
+            if tx.is_multiple_of(2) {
+                // Even destination x: source bytes line up with destination
+                // bytes directly, so copy the aligned portion verbatim.
+                let full_bytes = (copy_width / 2) as usize;
+                let dest_start = dest_row_start + (tx / 2) as usize;
+                self.framebuffer[dest_start..dest_start + full_bytes]
+                    .copy_from_slice(&data[src_row_start..src_row_start + full_bytes]);
+                // A trailing odd pixel only touches the high nibble.
+                if copy_width % 2 == 1 {
+                    let src_byte = data[src_row_start + full_bytes];
+                    let dest_index = dest_start + full_bytes;
+                    self.framebuffer[dest_index] =
+                        (self.framebuffer[dest_index] & 0x0F) | (src_byte & 0xF0);
+                }
+            } else {
+                // Odd destination x: every source byte straddles two
+                // destination bytes, so shift and merge nibble by nibble.
+                for col in 0..copy_width {
+                    let src_byte = data[src_row_start + (col as usize) / 2];
+                    let src_nibble = if col.is_multiple_of(2) {
+                        src_byte >> 4
+                    } else {
+                        src_byte & 0x0F
+                    };
+                    let dest_col = tx + col;
+                    let dest_index = dest_row_start + (dest_col / 2) as usize;
+                    if dest_col.is_multiple_of(2) {
+                        self.framebuffer[dest_index] =
+                            (self.framebuffer[dest_index] & 0x0F) | (src_nibble << 4);
+                    } else {
+                        self.framebuffer[dest_index] =
+                            (self.framebuffer[dest_index] & 0xF0) | src_nibble;
+                    }
+                }
+            }
+        }
+
+        self.mark_dirty(tx as u8, ty as u8);
+        self.mark_dirty((tx + copy_width - 1) as u8, (ty + copy_rows - 1) as u8);
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Shift the visible window down by `lines` rows (negative scrolls up)
+    /// by animating the RAM start line, instead of rewriting the framebuffer.
+    /// `draw_iter`/`flush` always address RAM row 0 as logical row 0, so
+    /// this is purely a hardware-side remap; rows newly scrolled into view
+    /// still hold whatever was last drawn there and must be redrawn by the
+    /// caller. Wraps around the panel's RAM rows (`height`).
+    ///
+    /// `DisplayStartLine` alone is sufficient for wrap correction: the panel
+    /// already wraps the GDDRAM row it maps to COM0 modulo the MUX ratio
+    /// internally, so `rem_euclid` here exactly mirrors what the hardware
+    /// does. `DisplayOffset` is a separate, static COM-to-row remap meant to
+    /// be set once (by `init()`, to 0) for the panel's physical wiring, not
+    /// animated per scroll step — an earlier version of this method also
+    /// advanced `DisplayOffset` in lock-step to "help" the wrap, but since
+    /// both registers offset the same mapping, that doubled up and canceled
+    /// the scroll outright. It is left untouched here; only
+    /// `DisplayStartLine` animates. This method operates on physical RAM
+    /// rows, so combining it with `set_rotation` other than `Rotate0` will
+    /// not scroll along the logical (rotated) axis.
+    pub fn scroll_vertical(&mut self, lines: i8) -> Result<(), I2C::Error> {
+        let total_rows = self.px_height() as i32;
+        let offset = (self.scroll_offset as i32 + lines as i32).rem_euclid(total_rows) as u8;
+        self.scroll_offset = offset;
+        self.send_cmd(Commands::DisplayStartLine(offset))
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Undo `scroll_vertical`, restoring the start line and offset to 0.
+    pub fn reset_scroll(&mut self) -> Result<(), I2C::Error> {
+        self.scroll_offset = 0;
+        self.send_cmd(Commands::DisplayStartLine(0))?;
+        self.send_cmd(Commands::DisplayOffset(0))
+    }
+}
+
+/// Panel rotation applied by [`SSD1327I2C::set_rotation`]
+#[cfg(feature = "graphics")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// No rotation (default)
+    Rotate0,
+    /// Rotate 90 degrees clockwise
+    Rotate90,
+    /// Rotate 180 degrees
+    Rotate180,
+    /// Rotate 270 degrees clockwise
+    Rotate270,
 }
 
 /// Commands to be sent to the SSD1327
@@ -278,6 +568,11 @@ pub enum Commands {
     SecondPreChargePeriod(u8),
     /// The default Lineear Gray Scale table (0xB9)
     LinearLUT,
+    /// Custom 16-level grayscale table: GS1..GS15 pulse widths (GS0 is fixed
+    /// at 0 and not sent). Entries must be monotonically increasing and
+    /// within the panel's maximum pulse width, see [`gamma_table`] (requires
+    /// the `graphics` feature) (0xB8)
+    GrayScaleTable([u8; 15]),
     /// Set pre-charge voltage level (0xBC)
     PreChargeVoltage(u8),
     /// Set COM deselect voltage level (0xBE)
@@ -293,8 +588,38 @@ pub enum Commands {
     CommandLock,
 }
 
+/// Build a 15-entry grayscale pulse-width table (GS1..GS15) for
+/// [`Commands::GrayScaleTable`] from a gamma exponent, mapping gray levels
+/// perceptually instead of the flat `LinearLUT` ramp. `max_pw` is the
+/// panel's maximum pulse width (the value GS15 would take for `gamma = 1.0`).
+/// Any equal or decreasing neighbor is bumped up by 1 to keep the result
+/// strictly increasing, as required by the panel, but every entry is also
+/// capped at `max_pw`. Where there is not enough room below `max_pw` (always
+/// for `max_pw < 15`, and for small `gamma` whose early entries already sit
+/// near `max_pw`) the trailing entries repeat `max_pw`, and the table is then
+/// only non-decreasing.
+///
+/// Requires the `graphics` feature: the pow/round math is implemented via
+/// `libm`, which (like `embedded-graphics-core`) is only pulled in for that
+/// feature, keeping the no-graphics build free of the extra dependency.
+#[cfg(feature = "graphics")]
+pub fn gamma_table(gamma: f32, max_pw: u8) -> [u8; 15] {
+    let mut table = [0u8; 15];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let frac = (i as f32 + 1.0) / 15.0;
+        let value = libm::powf(frac, gamma) * max_pw as f32;
+        *entry = (libm::roundf(value) as u8).min(max_pw);
+    }
+    for i in 1..table.len() {
+        if table[i] <= table[i - 1] {
+            table[i] = table[i - 1].saturating_add(1).min(max_pw);
+        }
+    }
+    table
+}
+
 #[cfg(feature = "graphics")]
-impl<I2C: I2c> DrawTarget for SSD1327I2C<I2C> {
+impl<I2C: I2c, const N: usize> DrawTarget for SSD1327I2C<I2C, N> {
     type Color = Gray4;
 
     type Error = I2C::Error;
@@ -303,11 +628,28 @@ impl<I2C: I2c> DrawTarget for SSD1327I2C<I2C> {
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
+        let (px_width, px_height) = (self.px_width(), self.px_height());
+        let (logical_w, logical_h) = match self.rotation {
+            Rotation::Rotate90 | Rotation::Rotate270 => (px_height, px_width),
+            Rotation::Rotate0 | Rotation::Rotate180 => (px_width, px_height),
+        };
         for Pixel(coord, color) in pixels.into_iter() {
             // Check if the pixel coordinates are out of bounds
-            if let Ok((x @ 0..=127, y @ 0..=127)) = coord.try_into() {
+            if let Ok((lx, ly)) = <(u32, u32)>::try_from(coord) {
+                if lx >= logical_w || ly >= logical_h {
+                    continue;
+                }
+                // Rotate the logical (embedded-graphics) coordinate into the
+                // panel's physical framebuffer coordinate.
+                let (x, y) = match self.rotation {
+                    Rotation::Rotate0 => (lx, ly),
+                    Rotation::Rotate90 => (ly, px_height - 1 - lx),
+                    Rotation::Rotate180 => (px_width - 1 - lx, px_height - 1 - ly),
+                    Rotation::Rotate270 => (px_width - 1 - ly, lx),
+                };
                 // Calculate the index in the framebuffer.
-                let index: u32 = x / 2 + y * 64;
+                let stride = self.width as u32;
+                let index: u32 = x / 2 + y * stride;
                 let mut new_byte = color.luma();
                 // 1 byte for 2 pixels so we need to shift the byte by 4 bits if the x coordinate is even
                 if x % 2 == 0 {
@@ -317,6 +659,7 @@ impl<I2C: I2c> DrawTarget for SSD1327I2C<I2C> {
                     self.framebuffer[index as usize] &= 0xF0;
                 }
                 self.framebuffer[index as usize] |= new_byte;
+                self.mark_dirty(x as u8, y as u8);
             }
         }
 
@@ -325,8 +668,89 @@ impl<I2C: I2c> DrawTarget for SSD1327I2C<I2C> {
 }
 
 #[cfg(feature = "graphics")]
-impl<I2C: I2c> OriginDimensions for SSD1327I2C<I2C> {
+impl<I2C: I2c, const N: usize> OriginDimensions for SSD1327I2C<I2C, N> {
     fn size(&self) -> Size {
-        Size::new(128, 128)
+        match self.rotation {
+            Rotation::Rotate90 | Rotation::Rotate270 => {
+                Size::new(self.px_height(), self.px_width())
+            }
+            Rotation::Rotate0 | Rotation::Rotate180 => Size::new(self.px_width(), self.px_height()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "graphics"))]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+
+    /// An I2C that records nothing and never fails, just enough to construct
+    /// a driver for the pure framebuffer/math tests below.
+    struct NoopI2c;
+
+    impl embedded_hal::i2c::ErrorType for NoopI2c {
+        type Error = Infallible;
+    }
+
+    impl embedded_hal::i2c::I2c for NoopI2c {
+        fn transaction(
+            &mut self,
+            _address: u8,
+            _operations: &mut [embedded_hal::i2c::Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn draw_raw_gray4_even_aligned_blit_round_trips() {
+        let mut driver = SSD1327I2C::<NoopI2c>::with_wh(NoopI2c, 8, 2);
+        // 8px wide, 2px per byte => 4 bytes/row, 2 rows.
+        let data = [0xAB, 0xCD, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+        driver.draw_raw_gray4(Point::new(0, 0), 8, &data);
+        assert_eq!(&driver.framebuffer[..data.len()], &data[..]);
+    }
+
+    #[test]
+    fn draw_raw_gray4_odd_x_shifts_nibbles() {
+        let mut driver = SSD1327I2C::<NoopI2c>::with_wh(NoopI2c, 8, 1);
+        // One source byte (two pixels: nibble 0xA, nibble 0xB) landing at an
+        // odd destination column straddles two destination bytes.
+        driver.draw_raw_gray4(Point::new(1, 0), 2, &[0xAB]);
+        assert_eq!(driver.framebuffer[0], 0x0A);
+        assert_eq!(driver.framebuffer[1], 0xB0);
+    }
+
+    #[test]
+    fn gamma_table_is_strictly_increasing_when_max_pw_allows_it() {
+        // 15 strictly increasing integer entries need max_pw >= 15; anything
+        // smaller can't satisfy both constraints (see the clamp test below).
+        let table = gamma_table(3.0, 15);
+        for i in 1..table.len() {
+            assert!(table[i] > table[i - 1], "table not strictly increasing: {table:?}");
+        }
+    }
+
+    #[test]
+    fn gamma_table_never_exceeds_max_pw() {
+        // A tight max_pw (here, smaller than the 15 entries needed for a
+        // strictly increasing ramp) forces the monotonicity bump to run out
+        // of room and repeat the ceiling value for the last few entries.
+        // That's an inherent tradeoff of clamping, not a bug: the important
+        // invariant is that the clamp still holds, which the earlier fix for
+        // this function's missing `.min(max_pw)` specifically targeted.
+        let table = gamma_table(3.0, 10);
+        for &v in &table {
+            assert!(v <= 10, "entry {v} exceeds max_pw: {table:?}");
+        }
+    }
+
+    #[test]
+    fn gamma_table_linear_matches_identity_ramp() {
+        // gamma = 1.0 is a straight line, so entry i should land on
+        // round((i+1)/15 * max_pw), already strictly increasing with no
+        // bumping needed.
+        let table = gamma_table(1.0, 15);
+        assert_eq!(table, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
     }
 }